@@ -1,17 +1,18 @@
 use image::{ImageBuffer, Rgb, RgbImage};
 use imageproc::geometric_transformations::{Interpolation, Projection, warp_into};
-use nalgebra::{Matrix2, Matrix3, Vector2};
+use nalgebra::{DMatrix, DVector, Matrix2, Matrix3, Vector2, Vector3};
 
 use crate::ml::{
     error::{MlError, MlResult},
     types::{AlignmentResult, DecodedImage, FaceDetection, FaceResult, to_face_id},
 };
 
-const FACE_SIZE: u32 = 112;
 const LAPLACIAN_HARD_THRESHOLD: f32 = 10.0;
-const REMOVE_SIDE_COLUMNS: usize = 56;
 
-const MOBILEFACENET_IDEAL_5_LANDMARKS: [[f32; 2]; 5] = [
+// The classic InsightFace/ArcFace 112x112 alignment template, normalized to
+// [0, 1]. Both presets below share this landmark geometry and the same
+// mean/scale; they differ in the channel layout and order of the tensor.
+const STANDARD_FACE_5_LANDMARKS: [[f32; 2]; 5] = [
     [38.2946 / 112.0, 51.6963 / 112.0],
     [73.5318 / 112.0, 51.5014 / 112.0],
     [56.0252 / 112.0, 71.7366 / 112.0],
@@ -19,6 +20,87 @@ const MOBILEFACENET_IDEAL_5_LANDMARKS: [[f32; 2]; 5] = [
     [70.7299 / 112.0, 92.2041 / 112.0],
 ];
 
+/// Pixel layout of the normalized face tensor handed to the embedding model.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChannelLayout {
+    /// Height-Width-Channel, i.e. channels are interleaved per pixel.
+    Hwc,
+    /// Channel-Height-Width, i.e. one plane per channel.
+    Chw,
+}
+
+/// Channel ordering of the normalized face tensor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ChannelOrder {
+    Rgb,
+    Bgr,
+}
+
+/// Everything needed to align a face crop for a specific embedding model:
+/// the output crop size, the reference landmark positions that crop is
+/// warped onto, and the pixel normalization/layout the model expects. This
+/// lets the crate target different embedding backbones without hardcoding
+/// their geometry and preprocessing into the alignment code.
+#[derive(Clone, Debug)]
+pub struct AlignmentTemplate {
+    pub output_size: u32,
+    pub reference_landmarks: [[f32; 2]; 5],
+    pub channel_mean: [f32; 3],
+    pub channel_scale: [f32; 3],
+    pub layout: ChannelLayout,
+    pub channel_order: ChannelOrder,
+}
+
+impl AlignmentTemplate {
+    /// The crate's original template: 112x112, MobileFaceNet's `(x/127.5 -
+    /// 1.0)` normalization, HWC layout, RGB channel order.
+    pub fn mobilefacenet() -> Self {
+        Self {
+            output_size: 112,
+            reference_landmarks: STANDARD_FACE_5_LANDMARKS,
+            channel_mean: [127.5; 3],
+            channel_scale: [1.0 / 127.5; 3],
+            layout: ChannelLayout::Hwc,
+            channel_order: ChannelOrder::Rgb,
+        }
+    }
+
+    /// A 112x112 ArcFace-compatible template: same reference landmarks, but
+    /// CHW layout with BGR channel order.
+    pub fn arcface() -> Self {
+        Self {
+            output_size: 112,
+            reference_landmarks: STANDARD_FACE_5_LANDMARKS,
+            channel_mean: [127.5; 3],
+            channel_scale: [1.0 / 127.5; 3],
+            layout: ChannelLayout::Chw,
+            channel_order: ChannelOrder::Bgr,
+        }
+    }
+}
+
+// Canonical 3D face model in millimetres, nose tip at the origin, for the same
+// [left_eye, right_eye, nose, left_mouth, right_mouth] ordering as the detector
+// keypoints. Rough adult-face proportions; only used to recover pose, not scale.
+// Y is down (eyes negative, mouth positive) to match image-space landmarks.
+const CANONICAL_FACE_MODEL_MM: [[f32; 3]; 5] = [
+    [-32.0, -32.0, -26.0],
+    [32.0, -32.0, -26.0],
+    [0.0, 0.0, 0.0],
+    [-28.0, 32.0, -24.0],
+    [28.0, 32.0, -24.0],
+];
+
+const HEAD_POSE_ANGLE_CLAMP_DEGREES: f32 = 90.0;
+const HEAD_POSE_GAUSS_NEWTON_ITERATIONS: usize = 20;
+const HEAD_POSE_CONVERGENCE_RESIDUAL_PX: f32 = 1.0;
+const HEAD_POSE_COLLINEARITY_EPSILON: f32 = 1e-4;
+
+const CLAHE_GRID_SIZE: u32 = 8;
+const CLAHE_CLIP_FACTOR: f32 = 3.0;
+
+const RANSAC_INLIER_THRESHOLD_PX: f32 = 8.0;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum FaceDirection {
     Left,
@@ -35,6 +117,8 @@ pub fn run_face_alignment(
     file_id: i64,
     decoded: &DecodedImage,
     detections: Vec<FaceDetection>,
+    normalize_illumination: bool,
+    template: &AlignmentTemplate,
 ) -> MlResult<(Vec<Vec<f32>>, Vec<FaceResult>)> {
     let source = rgb_image_from_decoded(decoded)?;
     let mut aligned_face_inputs = Vec::with_capacity(detections.len());
@@ -46,10 +130,28 @@ pub fn run_face_alignment(
             decoded.dimensions.width,
             decoded.dimensions.height,
         );
-        let alignment = estimate_similarity_transform(&absolute_detection.keypoints)?;
-        let aligned = warp_face_image(&source, &alignment.affine_matrix)?;
-        let normalized = normalize_face_rgb_for_mobilefacenet(&aligned);
-        let blur_value = compute_blur_value(&aligned, face_direction(&absolute_detection));
+        let (alignment, _landmark_inlier_mask, landmark_inlier_count) =
+            estimate_similarity_transform_ransac(&absolute_detection.keypoints, template)?;
+        let aligned = warp_face_image(&source, &alignment.affine_matrix, template.output_size)?;
+        let normalized = if normalize_illumination {
+            normalize_face_rgb(&apply_clahe(&aligned), template)
+        } else {
+            normalize_face_rgb(&aligned, template)
+        };
+        let direction = face_direction(&absolute_detection);
+        let blur_value = compute_blur_value(&aligned, direction);
+        let (yaw, pitch, roll) = estimate_head_pose(
+            &absolute_detection.keypoints,
+            decoded.dimensions.width as f32,
+            decoded.dimensions.height as f32,
+            direction,
+        );
+        let alignment = AlignmentResult {
+            yaw,
+            pitch,
+            roll,
+            ..alignment
+        };
         let face_id = to_face_id(file_id, detection.box_xyxy);
 
         aligned_face_inputs.push(normalized);
@@ -59,6 +161,7 @@ pub fn run_face_alignment(
             alignment,
             embedding: Vec::new(),
             face_id,
+            landmark_inlier_count: landmark_inlier_count as u8,
         });
     }
 
@@ -89,17 +192,17 @@ fn to_absolute_detection(
     FaceDetectionAbsolute { keypoints }
 }
 
-fn estimate_similarity_transform(src_points: &[[f32; 2]; 5]) -> MlResult<AlignmentResult> {
+fn estimate_similarity_transform(
+    src_points: &[[f32; 2]],
+    dst_points: &[[f32; 2]],
+) -> MlResult<AlignmentResult> {
     let src_mean = mean_2d(src_points);
-    let dst_mean = mean_2d(&MOBILEFACENET_IDEAL_5_LANDMARKS);
+    let dst_mean = mean_2d(dst_points);
     let n = src_points.len() as f32;
 
     let mut a = Matrix2::<f32>::zeros();
     let mut src_var_sum = 0.0f32;
-    for (src, dst) in src_points
-        .iter()
-        .zip(MOBILEFACENET_IDEAL_5_LANDMARKS.iter())
-    {
+    for (src, dst) in src_points.iter().zip(dst_points.iter()) {
         let src_d = Vector2::new(src[0] - src_mean.x, src[1] - src_mean.y);
         let dst_d = Vector2::new(dst[0] - dst_mean.x, dst[1] - dst_mean.y);
         a += dst_d * src_d.transpose();
@@ -181,9 +284,130 @@ fn estimate_similarity_transform(src_points: &[[f32; 2]; 5]) -> MlResult<Alignme
         center: [center[0], center[1]],
         size,
         rotation,
+        yaw: 0.0,
+        pitch: 0.0,
+        roll: 0.0,
     })
 }
 
+/// Similarity transform estimation that tolerates one or two badly localized
+/// landmarks. Every pair of points determines a similarity transform on its
+/// own (a similarity transform has 4 degrees of freedom); with only five
+/// landmarks it's cheap to exhaustively try all `C(5, 2) = 10` pairs rather
+/// than randomly sample, so that's what this does. The pair whose transform
+/// gets the most landmarks within `RANSAC_INLIER_THRESHOLD_PX` of the
+/// template's reference landmarks is kept, and the final fit is the ordinary
+/// Umeyama fit restricted to that pair's inlier set. When four or five
+/// landmarks agree, the ordinary fit over all five points is used instead,
+/// so a single outlier still takes part in the fit; the inlier-only refit
+/// applies only when three or fewer landmarks agree.
+/// Returns the refined alignment alongside which landmarks were judged
+/// inliers and how many, so callers can flag low-inlier faces as unreliable.
+fn estimate_similarity_transform_ransac(
+    src_points: &[[f32; 2]; 5],
+    template: &AlignmentTemplate,
+) -> MlResult<(AlignmentResult, [bool; 5], usize)> {
+    let reference_landmarks = &template.reference_landmarks;
+    let inlier_threshold = RANSAC_INLIER_THRESHOLD_PX / template.output_size as f32;
+
+    let mut best_mask = [false; 5];
+    let mut best_inlier_count = 0usize;
+    for i in 0..5 {
+        for j in (i + 1)..5 {
+            let Some(candidate) = fit_two_point_similarity(
+                src_points[i],
+                src_points[j],
+                reference_landmarks[i],
+                reference_landmarks[j],
+            ) else {
+                continue;
+            };
+
+            let mask = inlier_mask(&candidate, src_points, reference_landmarks, inlier_threshold);
+            let count = mask.iter().filter(|is_inlier| **is_inlier).count();
+            if count > best_inlier_count {
+                best_inlier_count = count;
+                best_mask = mask;
+            }
+        }
+    }
+
+    let alignment = if best_inlier_count >= 4 {
+        estimate_similarity_transform(src_points, reference_landmarks)?
+    } else {
+        let inlier_src: Vec<[f32; 2]> = (0..5)
+            .filter(|&i| best_mask[i])
+            .map(|i| src_points[i])
+            .collect();
+        let inlier_dst: Vec<[f32; 2]> = (0..5)
+            .filter(|&i| best_mask[i])
+            .map(|i| reference_landmarks[i])
+            .collect();
+
+        if inlier_src.len() >= 2 {
+            estimate_similarity_transform(&inlier_src, &inlier_dst)?
+        } else {
+            estimate_similarity_transform(src_points, reference_landmarks)?
+        }
+    };
+
+    let final_mask = inlier_mask(
+        &alignment.affine_matrix,
+        src_points,
+        reference_landmarks,
+        inlier_threshold,
+    );
+    let final_count = final_mask.iter().filter(|is_inlier| **is_inlier).count();
+
+    Ok((alignment, final_mask, final_count))
+}
+
+/// Closed-form similarity transform (scale + rotation + translation, no
+/// reflection) taking `src_a -> dst_a` and `src_b -> dst_b` exactly, using
+/// the standard complex-number formulation of a 2D similarity map.
+fn fit_two_point_similarity(
+    src_a: [f32; 2],
+    src_b: [f32; 2],
+    dst_a: [f32; 2],
+    dst_b: [f32; 2],
+) -> Option<[[f32; 3]; 3]> {
+    let src_d = [src_b[0] - src_a[0], src_b[1] - src_a[1]];
+    let dst_d = [dst_b[0] - dst_a[0], dst_b[1] - dst_a[1]];
+
+    let src_norm_sq = src_d[0] * src_d[0] + src_d[1] * src_d[1];
+    if src_norm_sq <= f32::EPSILON {
+        return None;
+    }
+
+    // z = dst_d / src_d as complex division; z encodes the scale+rotation.
+    let z_re = (dst_d[0] * src_d[0] + dst_d[1] * src_d[1]) / src_norm_sq;
+    let z_im = (dst_d[1] * src_d[0] - dst_d[0] * src_d[1]) / src_norm_sq;
+
+    let t_re = dst_a[0] - (z_re * src_a[0] - z_im * src_a[1]);
+    let t_im = dst_a[1] - (z_im * src_a[0] + z_re * src_a[1]);
+
+    Some([[z_re, -z_im, t_re], [z_im, z_re, t_im], [0.0, 0.0, 1.0]])
+}
+
+fn inlier_mask(
+    affine_matrix: &[[f32; 3]; 3],
+    src_points: &[[f32; 2]; 5],
+    reference_landmarks: &[[f32; 2]; 5],
+    threshold: f32,
+) -> [bool; 5] {
+    let mut mask = [false; 5];
+    for (i, src) in src_points.iter().enumerate() {
+        let projected = [
+            affine_matrix[0][0] * src[0] + affine_matrix[0][1] * src[1] + affine_matrix[0][2],
+            affine_matrix[1][0] * src[0] + affine_matrix[1][1] * src[1] + affine_matrix[1][2],
+        ];
+        let ideal = reference_landmarks[i];
+        let error = ((projected[0] - ideal[0]).powi(2) + (projected[1] - ideal[1]).powi(2)).sqrt();
+        mask[i] = error <= threshold;
+    }
+    mask
+}
+
 fn mean_2d(points: &[[f32; 2]]) -> Vector2<f32> {
     let mut sum = Vector2::new(0.0f32, 0.0f32);
     for point in points {
@@ -193,7 +417,11 @@ fn mean_2d(points: &[[f32; 2]]) -> Vector2<f32> {
     sum / points.len() as f32
 }
 
-fn warp_face_image(source: &RgbImage, affine_matrix: &[[f32; 3]; 3]) -> MlResult<RgbImage> {
+fn warp_face_image(
+    source: &RgbImage,
+    affine_matrix: &[[f32; 3]; 3],
+    output_size: u32,
+) -> MlResult<RgbImage> {
     let mut transform = [[0.0f32; 3]; 3];
     for row in 0..3 {
         for col in 0..3 {
@@ -201,7 +429,7 @@ fn warp_face_image(source: &RgbImage, affine_matrix: &[[f32; 3]; 3]) -> MlResult
             transform[row][col] = if (value - 1.0).abs() <= f32::EPSILON {
                 1.0
             } else {
-                value * FACE_SIZE as f32
+                value * output_size as f32
             };
         }
     }
@@ -219,7 +447,7 @@ fn warp_face_image(source: &RgbImage, affine_matrix: &[[f32; 3]; 3]) -> MlResult
     ])
     .ok_or_else(|| MlError::Postprocess("invalid affine matrix projection".to_string()))?;
 
-    let mut output = RgbImage::from_pixel(FACE_SIZE, FACE_SIZE, Rgb([114, 114, 114]));
+    let mut output = RgbImage::from_pixel(output_size, output_size, Rgb([114, 114, 114]));
     warp_into(
         source,
         &projection,
@@ -230,19 +458,238 @@ fn warp_face_image(source: &RgbImage, affine_matrix: &[[f32; 3]; 3]) -> MlResult
     Ok(output)
 }
 
-fn normalize_face_rgb_for_mobilefacenet(face_image: &RgbImage) -> Vec<f32> {
-    let mut output = Vec::with_capacity((FACE_SIZE * FACE_SIZE * 3) as usize);
-    for y in 0..FACE_SIZE {
-        for x in 0..FACE_SIZE {
+/// Applies contrast-limited adaptive histogram equalization to the luminance
+/// plane of an aligned face crop, leaving chroma untouched, so embeddings are
+/// less sensitive to uneven lighting and shadows.
+fn apply_clahe(face_image: &RgbImage) -> RgbImage {
+    let width = face_image.width();
+    let height = face_image.height();
+
+    let mut luma = vec![0u8; (width * height) as usize];
+    let mut cb = vec![0f32; (width * height) as usize];
+    let mut cr = vec![0f32; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let idx = (y * width + x) as usize;
             let px = face_image.get_pixel(x, y).0;
-            output.push(px[0] as f32 / 127.5 - 1.0);
-            output.push(px[1] as f32 / 127.5 - 1.0);
-            output.push(px[2] as f32 / 127.5 - 1.0);
+            let (r, g, b) = (px[0] as f32, px[1] as f32, px[2] as f32);
+            luma[idx] = (0.299 * r + 0.587 * g + 0.114 * b).round().clamp(0.0, 255.0) as u8;
+            cb[idx] = -0.168736 * r - 0.331264 * g + 0.5 * b + 128.0;
+            cr[idx] = 0.5 * r - 0.418688 * g - 0.081312 * b + 128.0;
         }
     }
+
+    let equalized_luma = clahe_equalize(&luma, width, height, CLAHE_GRID_SIZE, CLAHE_CLIP_FACTOR);
+
+    let mut output = ImageBuffer::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let idx = (y * width + x) as usize;
+            let new_y = equalized_luma[idx] as f32;
+            let (b_chroma, r_chroma) = (cb[idx] - 128.0, cr[idx] - 128.0);
+            let r = new_y + 1.402 * r_chroma;
+            let g = new_y - 0.344136 * b_chroma - 0.714136 * r_chroma;
+            let b = new_y + 1.772 * b_chroma;
+            output.put_pixel(
+                x,
+                y,
+                Rgb([
+                    r.round().clamp(0.0, 255.0) as u8,
+                    g.round().clamp(0.0, 255.0) as u8,
+                    b.round().clamp(0.0, 255.0) as u8,
+                ]),
+            );
+        }
+    }
+
     output
 }
 
+/// CLAHE over an 8-bit plane: per-tile clipped-histogram equalization with
+/// bilinear blending between neighbouring tiles to avoid block artifacts.
+fn clahe_equalize(
+    plane: &[u8],
+    width: u32,
+    height: u32,
+    grid_size: u32,
+    clip_factor: f32,
+) -> Vec<u8> {
+    let tile_width = width.div_ceil(grid_size).max(1);
+    let tile_height = height.div_ceil(grid_size).max(1);
+    let tiles_x = width.div_ceil(tile_width).max(1);
+    let tiles_y = height.div_ceil(tile_height).max(1);
+
+    let mut tile_luts = vec![[0u8; 256]; (tiles_x * tiles_y) as usize];
+    for tile_y in 0..tiles_y {
+        for tile_x in 0..tiles_x {
+            let x0 = tile_x * tile_width;
+            let y0 = tile_y * tile_height;
+            let x1 = (x0 + tile_width).min(width);
+            let y1 = (y0 + tile_height).min(height);
+
+            let mut histogram = [0u32; 256];
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    histogram[plane[(y * width + x) as usize] as usize] += 1;
+                }
+            }
+
+            let tile_pixels = ((x1 - x0) * (y1 - y0)).max(1);
+            let clip_limit = (clip_factor * (tile_pixels as f32 / 256.0)) as u32;
+
+            let mut excess = 0u32;
+            for bin in histogram.iter_mut() {
+                if *bin > clip_limit {
+                    excess += *bin - clip_limit;
+                    *bin = clip_limit;
+                }
+            }
+            let redistribution = excess / 256;
+            let remainder = excess % 256;
+            for (i, bin) in histogram.iter_mut().enumerate() {
+                *bin += redistribution + if (i as u32) < remainder { 1 } else { 0 };
+            }
+
+            let mut cdf = [0u32; 256];
+            let mut running = 0u32;
+            for (i, count) in histogram.iter().enumerate() {
+                running += count;
+                cdf[i] = running;
+            }
+
+            let lut = &mut tile_luts[(tile_y * tiles_x + tile_x) as usize];
+            if running == 0 {
+                for (i, value) in lut.iter_mut().enumerate() {
+                    *value = i as u8;
+                }
+            } else {
+                for (i, value) in lut.iter_mut().enumerate() {
+                    *value = ((cdf[i] as f32 / running as f32) * 255.0).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+
+    let tile_center = |tile_x: u32, tile_y: u32| -> (f32, f32) {
+        (
+            tile_x as f32 * tile_width as f32 + tile_width as f32 / 2.0,
+            tile_y as f32 * tile_height as f32 + tile_height as f32 / 2.0,
+        )
+    };
+
+    let mut output = vec![0u8; (width * height) as usize];
+    for y in 0..height {
+        for x in 0..width {
+            let tile_x = (x / tile_width).min(tiles_x - 1);
+            let tile_y = (y / tile_height).min(tiles_y - 1);
+            let (cx, cy) = tile_center(tile_x, tile_y);
+
+            let left_tile = if (x as f32) < cx && tile_x > 0 {
+                tile_x - 1
+            } else {
+                tile_x
+            };
+            let right_tile = if (x as f32) >= cx && tile_x + 1 < tiles_x {
+                tile_x + 1
+            } else {
+                tile_x
+            };
+            let top_tile = if (y as f32) < cy && tile_y > 0 {
+                tile_y - 1
+            } else {
+                tile_y
+            };
+            let bottom_tile = if (y as f32) >= cy && tile_y + 1 < tiles_y {
+                tile_y + 1
+            } else {
+                tile_y
+            };
+
+            let (left_cx, _) = tile_center(left_tile, tile_y);
+            let (right_cx, _) = tile_center(right_tile, tile_y);
+            let (_, top_cy) = tile_center(tile_x, top_tile);
+            let (_, bottom_cy) = tile_center(tile_x, bottom_tile);
+
+            let wx = if (right_cx - left_cx).abs() > f32::EPSILON {
+                ((x as f32 - left_cx) / (right_cx - left_cx)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let wy = if (bottom_cy - top_cy).abs() > f32::EPSILON {
+                ((y as f32 - top_cy) / (bottom_cy - top_cy)).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+
+            let value = plane[(y * width + x) as usize] as usize;
+            let top_left = tile_luts[(top_tile * tiles_x + left_tile) as usize][value] as f32;
+            let top_right = tile_luts[(top_tile * tiles_x + right_tile) as usize][value] as f32;
+            let bottom_left = tile_luts[(bottom_tile * tiles_x + left_tile) as usize][value] as f32;
+            let bottom_right = tile_luts[(bottom_tile * tiles_x + right_tile) as usize][value] as f32;
+
+            let top = top_left * (1.0 - wx) + top_right * wx;
+            let bottom = bottom_left * (1.0 - wx) + bottom_right * wx;
+            let interpolated = top * (1.0 - wy) + bottom * wy;
+
+            output[(y * width + x) as usize] = interpolated.round().clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    output
+}
+
+/// Converts an aligned RGB crop into the flat tensor an embedding model
+/// expects, per `template`: `(channel - mean) * scale`, reordered to BGR if
+/// requested, and laid out HWC or CHW.
+fn normalize_face_rgb(face_image: &RgbImage, template: &AlignmentTemplate) -> Vec<f32> {
+    let width = face_image.width();
+    let height = face_image.height();
+    let mut channels = [
+        Vec::with_capacity((width * height) as usize),
+        Vec::with_capacity((width * height) as usize),
+        Vec::with_capacity((width * height) as usize),
+    ];
+
+    for y in 0..height {
+        for x in 0..width {
+            let px = face_image.get_pixel(x, y).0;
+            for channel in 0..3 {
+                let value = px[channel] as f32;
+                channels[channel].push(
+                    (value - template.channel_mean[channel]) * template.channel_scale[channel],
+                );
+            }
+        }
+    }
+
+    let channel_order: [usize; 3] = match template.channel_order {
+        ChannelOrder::Rgb => [0, 1, 2],
+        ChannelOrder::Bgr => [2, 1, 0],
+    };
+
+    match template.layout {
+        ChannelLayout::Chw => channel_order
+            .iter()
+            .flat_map(|&channel| channels[channel].iter().copied())
+            .collect(),
+        ChannelLayout::Hwc => {
+            let pixel_count = (width * height) as usize;
+            let mut output = Vec::with_capacity(pixel_count * 3);
+            let [a, b, c] = channel_order;
+            for ((first, second), third) in channels[a]
+                .iter()
+                .zip(channels[b].iter())
+                .zip(channels[c].iter())
+            {
+                output.push(*first);
+                output.push(*second);
+                output.push(*third);
+            }
+            output
+        }
+    }
+}
+
 fn face_direction(detection: &FaceDetectionAbsolute) -> FaceDirection {
     let left_eye = detection.keypoints[0];
     let right_eye = detection.keypoints[1];
@@ -274,6 +721,207 @@ fn face_direction(detection: &FaceDetectionAbsolute) -> FaceDirection {
     }
 }
 
+/// Estimates head pose (yaw, pitch, roll, in degrees) from the five 2D
+/// landmarks by solving perspective-n-point against `CANONICAL_FACE_MODEL_MM`.
+/// Falls back to a coarse estimate derived from the Left/Right/Straight
+/// heuristic when the landmarks are too close to collinear to constrain a
+/// 3D rotation.
+fn estimate_head_pose(
+    keypoints: &[[f32; 2]; 5],
+    image_width: f32,
+    image_height: f32,
+    fallback_direction: FaceDirection,
+) -> (f32, f32, f32) {
+    if image_width <= 0.0 || image_height <= 0.0 || points_are_collinear(keypoints) {
+        return fallback_head_pose(fallback_direction);
+    }
+
+    let focal = image_width;
+    let cx = image_width / 2.0;
+    let cy = image_height / 2.0;
+
+    let Some(mut params) = initial_pose_guess(keypoints, focal, cx, cy) else {
+        return fallback_head_pose(fallback_direction);
+    };
+
+    for _ in 0..HEAD_POSE_GAUSS_NEWTON_ITERATIONS {
+        let residuals = reprojection_residuals(&params, keypoints, focal, cx, cy);
+        if residuals.norm() < HEAD_POSE_CONVERGENCE_RESIDUAL_PX {
+            break;
+        }
+        let jacobian = numerical_jacobian(&params, keypoints, focal, cx, cy, &residuals);
+
+        // Damped normal equations (Levenberg-Marquardt style) so a
+        // near-singular Jacobian (e.g. a near-frontal face) doesn't blow up
+        // the step.
+        let jt = jacobian.transpose();
+        let mut jtj = &jt * &jacobian;
+        for i in 0..6 {
+            jtj[(i, i)] += 1e-3;
+        }
+        let rhs = &jt * &residuals;
+        let Some(delta) = jtj.lu().solve(&(-rhs)) else {
+            break;
+        };
+        params += delta;
+    }
+
+    let rvec = Vector3::new(params[0], params[1], params[2]);
+    let rotation = rodrigues_to_matrix(&rvec);
+    let (yaw, pitch, roll) = rotation_to_euler_zyx(&rotation);
+
+    (
+        yaw.clamp(-HEAD_POSE_ANGLE_CLAMP_DEGREES, HEAD_POSE_ANGLE_CLAMP_DEGREES),
+        pitch.clamp(-HEAD_POSE_ANGLE_CLAMP_DEGREES, HEAD_POSE_ANGLE_CLAMP_DEGREES),
+        roll.clamp(-HEAD_POSE_ANGLE_CLAMP_DEGREES, HEAD_POSE_ANGLE_CLAMP_DEGREES),
+    )
+}
+
+fn fallback_head_pose(direction: FaceDirection) -> (f32, f32, f32) {
+    let yaw = match direction {
+        FaceDirection::Left => -45.0,
+        FaceDirection::Right => 45.0,
+        FaceDirection::Straight => 0.0,
+    };
+    (yaw, 0.0, 0.0)
+}
+
+fn points_are_collinear(points: &[[f32; 2]; 5]) -> bool {
+    let mean = mean_2d(points);
+    let mut cov = Matrix2::<f32>::zeros();
+    for point in points {
+        let d = Vector2::new(point[0] - mean.x, point[1] - mean.y);
+        cov += d * d.transpose();
+    }
+    cov /= points.len() as f32;
+
+    let trace = cov[(0, 0)] + cov[(1, 1)];
+    let det = cov.determinant();
+    let discriminant = (trace * trace - 4.0 * det).max(0.0).sqrt();
+    let smallest_eigenvalue = (trace - discriminant) / 2.0;
+    let largest_eigenvalue = (trace + discriminant) / 2.0;
+
+    largest_eigenvalue <= f32::EPSILON
+        || smallest_eigenvalue / largest_eigenvalue < HEAD_POSE_COLLINEARITY_EPSILON
+}
+
+/// Weak-perspective / POSIT-style initial guess: assume the face is roughly
+/// frontal, estimate depth from the ratio between the projected and true
+/// inter-ocular distance, and back out a translation that places the
+/// model's centroid at the observed centroid.
+fn initial_pose_guess(
+    keypoints: &[[f32; 2]; 5],
+    focal: f32,
+    cx: f32,
+    cy: f32,
+) -> Option<DVector<f32>> {
+    let observed_eye_distance = ((keypoints[1][0] - keypoints[0][0]).powi(2)
+        + (keypoints[1][1] - keypoints[0][1]).powi(2))
+    .sqrt();
+    let model_eye_distance = ((CANONICAL_FACE_MODEL_MM[1][0] - CANONICAL_FACE_MODEL_MM[0][0])
+        .powi(2)
+        + (CANONICAL_FACE_MODEL_MM[1][1] - CANONICAL_FACE_MODEL_MM[0][1]).powi(2))
+    .sqrt();
+
+    if observed_eye_distance <= f32::EPSILON {
+        return None;
+    }
+
+    let depth = focal * model_eye_distance / observed_eye_distance;
+    let mean_point = mean_2d(keypoints);
+    let tx = (mean_point.x - cx) * depth / focal;
+    let ty = (mean_point.y - cy) * depth / focal;
+
+    Some(DVector::from_vec(vec![0.0, 0.0, 0.0, tx, ty, depth]))
+}
+
+fn project_point(point: &[f32; 3], rotation: &Matrix3<f32>, translation: &Vector3<f32>, focal: f32, cx: f32, cy: f32) -> Vector2<f32> {
+    let model = Vector3::new(point[0], point[1], point[2]);
+    let camera_point = rotation * model + translation;
+    let z = if camera_point.z.abs() > f32::EPSILON {
+        camera_point.z
+    } else {
+        f32::EPSILON
+    };
+    Vector2::new(
+        focal * camera_point.x / z + cx,
+        focal * camera_point.y / z + cy,
+    )
+}
+
+fn reprojection_residuals(
+    params: &DVector<f32>,
+    observed: &[[f32; 2]; 5],
+    focal: f32,
+    cx: f32,
+    cy: f32,
+) -> DVector<f32> {
+    let rvec = Vector3::new(params[0], params[1], params[2]);
+    let tvec = Vector3::new(params[3], params[4], params[5]);
+    let rotation = rodrigues_to_matrix(&rvec);
+
+    let mut residuals = DVector::zeros(10);
+    for (i, model_point) in CANONICAL_FACE_MODEL_MM.iter().enumerate() {
+        let projected = project_point(model_point, &rotation, &tvec, focal, cx, cy);
+        residuals[2 * i] = projected.x - observed[i][0];
+        residuals[2 * i + 1] = projected.y - observed[i][1];
+    }
+    residuals
+}
+
+fn numerical_jacobian(
+    params: &DVector<f32>,
+    observed: &[[f32; 2]; 5],
+    focal: f32,
+    cx: f32,
+    cy: f32,
+    base_residuals: &DVector<f32>,
+) -> DMatrix<f32> {
+    const EPSILON: f32 = 1e-4;
+    let mut jacobian = DMatrix::zeros(10, 6);
+    for col in 0..6 {
+        let mut perturbed = params.clone();
+        perturbed[col] += EPSILON;
+        let perturbed_residuals = reprojection_residuals(&perturbed, observed, focal, cx, cy);
+        for row in 0..10 {
+            jacobian[(row, col)] = (perturbed_residuals[row] - base_residuals[row]) / EPSILON;
+        }
+    }
+    jacobian
+}
+
+/// Rodrigues' rotation formula: axis-angle vector to a rotation matrix.
+fn rodrigues_to_matrix(rvec: &Vector3<f32>) -> Matrix3<f32> {
+    let theta = rvec.norm();
+    if theta <= f32::EPSILON {
+        return Matrix3::identity();
+    }
+
+    let axis = rvec / theta;
+    let k = Matrix3::new(
+        0.0, -axis.z, axis.y, axis.z, 0.0, -axis.x, -axis.y, axis.x, 0.0,
+    );
+
+    Matrix3::identity() + k * theta.sin() + (k * k) * (1.0 - theta.cos())
+}
+
+/// Converts a rotation matrix to (yaw, pitch, roll) Euler angles in degrees
+/// using the ZYX convention (yaw about Y, pitch about X, roll about Z).
+fn rotation_to_euler_zyx(r: &Matrix3<f32>) -> (f32, f32, f32) {
+    let sy = (-r[(2, 0)]).clamp(-1.0, 1.0);
+    let yaw = sy.asin();
+
+    let (pitch, roll) = if yaw.cos().abs() > 1e-6 {
+        (r[(2, 1)].atan2(r[(2, 2)]), r[(1, 0)].atan2(r[(0, 0)]))
+    } else {
+        // Gimbal lock: roll and pitch trade off, so arbitrarily collapse
+        // pitch into roll.
+        (0.0, r[(0, 1)].atan2(r[(1, 1)]))
+    };
+
+    (yaw.to_degrees(), pitch.to_degrees(), roll.to_degrees())
+}
+
 fn compute_blur_value(face_image: &RgbImage, direction: FaceDirection) -> f32 {
     let (gray, gray_rows, gray_cols) = to_grayscale_buffer(face_image);
     let (padded, padded_rows, padded_cols) =
@@ -329,13 +977,18 @@ fn pad_image_for_direction(
     cols: usize,
     direction: FaceDirection,
 ) -> (Vec<i32>, usize, usize) {
-    let padded_cols = cols + 2 - REMOVE_SIDE_COLUMNS;
+    // Removes half the columns, biased towards the side the face is turned
+    // towards (where the nose points), so blur is judged on the side of the crop carrying the
+    // actual face rather than background. Proportional to crop width so it
+    // still makes sense at alignment template sizes other than 112x112.
+    let remove_side_columns = cols / 2;
+    let padded_cols = cols + 2 - remove_side_columns;
     let padded_rows = rows + 2;
     let mut padded = vec![0i32; padded_rows * padded_cols];
 
     let start_col = match direction {
-        FaceDirection::Straight => REMOVE_SIDE_COLUMNS / 2,
-        FaceDirection::Left => REMOVE_SIDE_COLUMNS,
+        FaceDirection::Straight => remove_side_columns / 2,
+        FaceDirection::Left => remove_side_columns,
         FaceDirection::Right => 0,
     };
     let copy_cols = padded_cols.saturating_sub(2);
@@ -382,3 +1035,67 @@ fn variance_2d(matrix: &[i32], rows: usize, cols: usize) -> f32 {
     }
     variance / total
 }
+
+#[cfg(test)]
+mod euler_tests {
+    use super::*;
+
+    /// Builds R = Rz(roll) * Ry(yaw) * Rx(pitch), the inverse of
+    /// `rotation_to_euler_zyx`, from angles in degrees.
+    fn euler_to_rotation(yaw_deg: f32, pitch_deg: f32, roll_deg: f32) -> Matrix3<f32> {
+        let (sy, cy) = yaw_deg.to_radians().sin_cos();
+        let (sp, cp) = pitch_deg.to_radians().sin_cos();
+        let (sr, cr) = roll_deg.to_radians().sin_cos();
+
+        let rz = Matrix3::new(cr, -sr, 0.0, sr, cr, 0.0, 0.0, 0.0, 1.0);
+        let ry = Matrix3::new(cy, 0.0, sy, 0.0, 1.0, 0.0, -sy, 0.0, cy);
+        let rx = Matrix3::new(1.0, 0.0, 0.0, 0.0, cp, -sp, 0.0, sp, cp);
+
+        rz * ry * rx
+    }
+
+    #[test]
+    fn rotation_to_euler_zyx_round_trips_yaw_pitch_roll() {
+        let r = euler_to_rotation(35.0, -12.0, 8.0);
+        let (yaw, pitch, roll) = rotation_to_euler_zyx(&r);
+
+        assert!((yaw - 35.0).abs() < 1e-3, "yaw = {yaw}");
+        assert!((pitch - (-12.0)).abs() < 1e-3, "pitch = {pitch}");
+        assert!((roll - 8.0).abs() < 1e-3, "roll = {roll}");
+    }
+
+    #[test]
+    fn rotation_to_euler_zyx_isolates_pure_yaw() {
+        let r = euler_to_rotation(60.0, 0.0, 0.0);
+        let (yaw, pitch, roll) = rotation_to_euler_zyx(&r);
+
+        assert!((yaw - 60.0).abs() < 1e-3, "yaw = {yaw}");
+        assert!(pitch.abs() < 1e-3, "pitch = {pitch}");
+        assert!(roll.abs() < 1e-3, "roll = {roll}");
+    }
+
+    #[test]
+    fn estimate_head_pose_converges_for_a_frontal_face() {
+        let focal = 400.0;
+        let cx = 200.0;
+        let cy = 200.0;
+        let translation = Vector3::new(0.0, 0.0, 500.0);
+
+        let keypoints: Vec<[f32; 2]> = CANONICAL_FACE_MODEL_MM
+            .iter()
+            .map(|point| {
+                let projected =
+                    project_point(point, &Matrix3::identity(), &translation, focal, cx, cy);
+                [projected.x, projected.y]
+            })
+            .collect();
+        let keypoints: [[f32; 2]; 5] = keypoints.try_into().unwrap();
+
+        let (yaw, pitch, roll) =
+            estimate_head_pose(&keypoints, 2.0 * cx, 2.0 * cy, FaceDirection::Straight);
+
+        assert!(yaw.abs() < 1.0, "yaw = {yaw}");
+        assert!(pitch.abs() < 1.0, "pitch = {pitch}");
+        assert!(roll.abs() < 1.0, "roll = {roll}");
+    }
+}
@@ -0,0 +1,195 @@
+use crate::ml::types::FaceResult;
+
+const DEFAULT_COSINE_SIMILARITY_THRESHOLD: f32 = 0.62;
+
+/// A group of faces believed to belong to the same person, identified by a
+/// representative centroid embedding (the mean of its members' L2-normalized
+/// embeddings, re-normalized).
+#[derive(Clone, Debug)]
+pub struct FaceCluster {
+    pub centroid: Vec<f32>,
+    pub members: Vec<usize>,
+}
+
+struct StoredFace {
+    face_id: String,
+    embedding: Vec<f32>,
+}
+
+/// Groups `FaceResult` embeddings into identity clusters using single-linkage
+/// agglomerative clustering over cosine similarity, and supports assigning
+/// new faces to existing clusters incrementally as photos are indexed.
+pub struct FaceClusterer {
+    faces: Vec<StoredFace>,
+    clusters: Vec<FaceCluster>,
+}
+
+impl FaceClusterer {
+    /// Clusters `faces` from scratch using the default similarity threshold.
+    pub fn cluster(faces: &[FaceResult]) -> Self {
+        Self::cluster_with_threshold(faces, DEFAULT_COSINE_SIMILARITY_THRESHOLD)
+    }
+
+    /// Clusters `faces` from scratch via single-linkage agglomerative
+    /// clustering: any pair whose cosine similarity exceeds `threshold` is
+    /// unioned, and every resulting connected component becomes a cluster.
+    pub fn cluster_with_threshold(faces: &[FaceResult], threshold: f32) -> Self {
+        let stored: Vec<StoredFace> = faces
+            .iter()
+            .map(|face| StoredFace {
+                face_id: face.face_id.clone(),
+                embedding: l2_normalize(&face.embedding),
+            })
+            .collect();
+
+        let mut union_find = UnionFind::new(stored.len());
+        for i in 0..stored.len() {
+            for j in (i + 1)..stored.len() {
+                if cosine_similarity(&stored[i].embedding, &stored[j].embedding) > threshold {
+                    union_find.union(i, j);
+                }
+            }
+        }
+
+        let mut members_by_root: Vec<Vec<usize>> = vec![Vec::new(); stored.len()];
+        for i in 0..stored.len() {
+            members_by_root[union_find.find(i)].push(i);
+        }
+
+        let clusters = members_by_root
+            .into_iter()
+            .filter(|members| !members.is_empty())
+            .map(|members| {
+                let centroid = centroid_of(&members, &stored);
+                FaceCluster { centroid, members }
+            })
+            .collect();
+
+        Self {
+            faces: stored,
+            clusters,
+        }
+    }
+
+    pub fn clusters(&self) -> &[FaceCluster] {
+        &self.clusters
+    }
+
+    /// Matches `face` against existing cluster centroids (nearest centroid
+    /// above `threshold`), joining that cluster if found, or starting a new
+    /// one otherwise. Returns the index of the cluster the face joined.
+    pub fn assign_to_existing(&mut self, face: &FaceResult, threshold: f32) -> usize {
+        let embedding = l2_normalize(&face.embedding);
+        let face_index = self.faces.len();
+        self.faces.push(StoredFace {
+            face_id: face.face_id.clone(),
+            embedding: embedding.clone(),
+        });
+
+        let best_cluster = self
+            .clusters
+            .iter()
+            .enumerate()
+            .map(|(index, cluster)| (index, cosine_similarity(&embedding, &cluster.centroid)))
+            .filter(|(_, similarity)| *similarity > threshold)
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index);
+
+        match best_cluster {
+            Some(index) => {
+                self.clusters[index].members.push(face_index);
+                self.clusters[index].centroid =
+                    centroid_of(&self.clusters[index].members, &self.faces);
+                index
+            }
+            None => {
+                self.clusters.push(FaceCluster {
+                    centroid: embedding,
+                    members: vec![face_index],
+                });
+                self.clusters.len() - 1
+            }
+        }
+    }
+
+    /// Returns the `k` stored faces with the highest cosine similarity to
+    /// `embedding`, most similar first.
+    pub fn k_nearest(&self, embedding: &[f32], k: usize) -> Vec<(&str, f32)> {
+        let query = l2_normalize(embedding);
+        let mut similarities: Vec<(&str, f32)> = self
+            .faces
+            .iter()
+            .map(|face| {
+                (
+                    face.face_id.as_str(),
+                    cosine_similarity(&query, &face.embedding),
+                )
+            })
+            .collect();
+        similarities.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        similarities.truncate(k);
+        similarities
+    }
+}
+
+fn centroid_of(members: &[usize], stored: &[StoredFace]) -> Vec<f32> {
+    let dims = members
+        .first()
+        .map(|&index| stored[index].embedding.len())
+        .unwrap_or(0);
+    let mut sum = vec![0.0f32; dims];
+    for &index in members {
+        for (d, value) in stored[index].embedding.iter().enumerate() {
+            sum[d] += value;
+        }
+    }
+    l2_normalize(&sum)
+}
+
+/// Cosine similarity between two L2-normalized vectors, i.e. their dot product.
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn l2_normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm <= f32::EPSILON {
+        return embedding.to_vec();
+    }
+    embedding.iter().map(|v| v / norm).collect()
+}
+
+/// Union-find with path compression and union by size, used to build
+/// connected components for single-linkage clustering.
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(count: usize) -> Self {
+        Self {
+            parent: (0..count).collect(),
+            size: vec![1; count],
+        }
+    }
+
+    fn find(&mut self, node: usize) -> usize {
+        if self.parent[node] != node {
+            self.parent[node] = self.find(self.parent[node]);
+        }
+        self.parent[node]
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut root_a, mut root_b) = (self.find(a), self.find(b));
+        if root_a == root_b {
+            return;
+        }
+        if self.size[root_a] < self.size[root_b] {
+            std::mem::swap(&mut root_a, &mut root_b);
+        }
+        self.parent[root_b] = root_a;
+        self.size[root_a] += self.size[root_b];
+    }
+}